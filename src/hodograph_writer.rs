@@ -0,0 +1,229 @@
+use crate::Result;
+use std::io::Write;
+
+// Altitude/wind metadata carried by one colored hodograph segment.
+#[derive(Debug, Clone, Copy)]
+pub struct SegmentMeta {
+    pub altitude: f32,
+    pub wind_dir: f32,
+    pub wind_spd: f32,
+}
+
+// Renders the geometry VadClient computes (range rings, colored hodograph
+// segments, storm-motion markers) into a concrete output format. The
+// lat/lon projection math stays in VadClient and is shared by every
+// implementation of this trait.
+pub trait HodographWriter {
+    // An unstyled polyline, e.g. a range ring. Points are (lat, lon).
+    fn ring(&mut self, points: &[(f32, f32)]) -> Result<()>;
+
+    // A colored hodograph segment, labeled with the VWP annotation text.
+    fn segment(
+        &mut self,
+        points: &[(f32, f32)],
+        color: (u8, u8, u8),
+        label: &str,
+        meta: SegmentMeta,
+    ) -> Result<()>;
+
+    // A labeled point marker, e.g. a Bunkers storm-motion vector.
+    fn marker(&mut self, point: (f32, f32), label: &str) -> Result<()>;
+
+    // Flush the accumulated output to `out`.
+    fn finish(&mut self, out: &mut dyn Write) -> Result<()>;
+}
+
+// Renders GRLevelX placefile syntax, as vad_client has always produced.
+#[derive(Default)]
+pub struct GrLevelXWriter {
+    buf: Vec<u8>,
+}
+
+impl HodographWriter for GrLevelXWriter {
+    fn ring(&mut self, points: &[(f32, f32)]) -> Result<()> {
+        writeln!(self.buf, "Color: 100 100 100")?;
+        writeln!(self.buf, "Line: 1, 0")?;
+
+        for (x, y) in points {
+            writeln!(self.buf, "{x}, {y}")?;
+        }
+
+        writeln!(self.buf, "End:\n")?;
+
+        Ok(())
+    }
+
+    fn segment(
+        &mut self,
+        points: &[(f32, f32)],
+        color: (u8, u8, u8),
+        label: &str,
+        _meta: SegmentMeta,
+    ) -> Result<()> {
+        writeln!(
+            self.buf,
+            "Color: {} {} {}",
+            color.0, color.1, color.2
+        )?;
+        writeln!(self.buf, "Line: 3, 0, \"{label}\"")?;
+
+        for (x, y) in points {
+            writeln!(self.buf, "{x}, {y}")?;
+        }
+
+        writeln!(self.buf, "End:\n")?;
+
+        Ok(())
+    }
+
+    fn marker(&mut self, point: (f32, f32), label: &str) -> Result<()> {
+        writeln!(self.buf, "Object: {}, {}", point.0, point.1)?;
+        writeln!(self.buf, "Threshold: 999")?;
+        writeln!(self.buf, "Text: 0, 0, 1, \"{label}\"")?;
+        writeln!(self.buf, "End:\n")?;
+
+        Ok(())
+    }
+
+    fn finish(&mut self, out: &mut dyn Write) -> Result<()> {
+        out.write_all(&self.buf)?;
+
+        Ok(())
+    }
+}
+
+fn json_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn linestring_coords(points: &[(f32, f32)]) -> String {
+    // GeoJSON coordinates are `[lon, lat]`, the reverse of the `(lat, lon)`
+    // points the rest of the codebase passes around.
+    points
+        .iter()
+        .map(|(lat, lon)| format!("[{lon},{lat}]"))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+// Renders each element as a GeoJSON Feature, for consumption by web maps
+// (Leaflet/MapLibre) and desktop GIS.
+#[derive(Default)]
+pub struct GeoJsonWriter {
+    features: Vec<String>,
+}
+
+impl HodographWriter for GeoJsonWriter {
+    fn ring(&mut self, points: &[(f32, f32)]) -> Result<()> {
+        self.features.push(format!(
+            r#"{{"type":"Feature","properties":{{"kind":"range_ring"}},"geometry":{{"type":"LineString","coordinates":[{}]}}}}"#,
+            linestring_coords(points)
+        ));
+
+        Ok(())
+    }
+
+    fn segment(
+        &mut self,
+        points: &[(f32, f32)],
+        _color: (u8, u8, u8),
+        label: &str,
+        meta: SegmentMeta,
+    ) -> Result<()> {
+        self.features.push(format!(
+            r#"{{"type":"Feature","properties":{{"altitude":{},"wind_dir":{},"wind_spd":{},"label":"{}"}},"geometry":{{"type":"LineString","coordinates":[{}]}}}}"#,
+            meta.altitude,
+            meta.wind_dir,
+            meta.wind_spd,
+            json_escape(label),
+            linestring_coords(points)
+        ));
+
+        Ok(())
+    }
+
+    fn marker(&mut self, point: (f32, f32), label: &str) -> Result<()> {
+        let (lat, lon) = point;
+        self.features.push(format!(
+            r#"{{"type":"Feature","properties":{{"label":"{}"}},"geometry":{{"type":"Point","coordinates":[{lon},{lat}]}}}}"#,
+            json_escape(label)
+        ));
+
+        Ok(())
+    }
+
+    // Emits just the comma-joined `Feature`s, not a `FeatureCollection`
+    // wrapper, so the caller can merge fragments from multiple radars into
+    // one collection.
+    fn finish(&mut self, out: &mut dyn Write) -> Result<()> {
+        out.write_all(self.features.join(",").as_bytes())?;
+
+        Ok(())
+    }
+}
+
+fn kml_coords(points: &[(f32, f32)]) -> String {
+    points
+        .iter()
+        .map(|(lat, lon)| format!("{lon},{lat},0"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+// Renders each element as a KML Placemark, for desktop GIS tools.
+#[derive(Default)]
+pub struct KmlWriter {
+    placemarks: Vec<String>,
+}
+
+impl HodographWriter for KmlWriter {
+    fn ring(&mut self, points: &[(f32, f32)]) -> Result<()> {
+        self.placemarks.push(format!(
+            "<Placemark><LineString><coordinates>{}</coordinates></LineString></Placemark>",
+            kml_coords(points)
+        ));
+
+        Ok(())
+    }
+
+    fn segment(
+        &mut self,
+        points: &[(f32, f32)],
+        _color: (u8, u8, u8),
+        label: &str,
+        meta: SegmentMeta,
+    ) -> Result<()> {
+        self.placemarks.push(format!(
+            "<Placemark><name>{}</name><description>altitude={} wind={}/{}</description><LineString><coordinates>{}</coordinates></LineString></Placemark>",
+            xml_escape(label),
+            meta.altitude,
+            meta.wind_dir,
+            meta.wind_spd,
+            kml_coords(points)
+        ));
+
+        Ok(())
+    }
+
+    fn marker(&mut self, point: (f32, f32), label: &str) -> Result<()> {
+        let (lat, lon) = point;
+        self.placemarks.push(format!(
+            "<Placemark><name>{}</name><Point><coordinates>{lon},{lat},0</coordinates></Point></Placemark>",
+            xml_escape(label)
+        ));
+
+        Ok(())
+    }
+
+    // Emits just the `Placemark`s, not the `<kml><Document>` wrapper, so the
+    // caller can merge fragments from multiple radars into one document.
+    fn finish(&mut self, out: &mut dyn Write) -> Result<()> {
+        out.write_all(self.placemarks.join("").as_bytes())?;
+
+        Ok(())
+    }
+}
@@ -1,10 +1,13 @@
-use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
 
+mod hodograph_writer;
 mod vad_client;
 mod vad_file;
 mod vad_params;
 
-use vad_client::VadClient;
+use clap::{Parser, ValueEnum};
+use vad_client::{Config, OutputFormat, VadClient};
 pub use vad_file::VadFile;
 pub use vad_params::{VadMessage, VadProfile};
 
@@ -24,14 +27,95 @@ impl std::fmt::Display for VadError {
 
 impl std::error::Error for VadError {}
 
+/// Output format for the rendered hodographs.
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Format {
+    /// GRLevelX placefile syntax.
+    GrLevelX,
+    /// GeoJSON FeatureCollection, for Leaflet/MapLibre.
+    GeoJson,
+    /// KML, for desktop GIS tools.
+    Kml,
+}
+
+impl std::fmt::Display for Format {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.to_possible_value()
+            .expect("no skipped variants")
+            .get_name()
+            .fmt(f)
+    }
+}
+
+impl From<Format> for OutputFormat {
+    fn from(format: Format) -> Self {
+        match format {
+            Format::GrLevelX => OutputFormat::GrLevelX,
+            Format::GeoJson => OutputFormat::GeoJson,
+            Format::Kml => OutputFormat::Kml,
+        }
+    }
+}
+
+/// Poll NOAA for VAD Wind Profile products and render GRLevelX hodograph placefiles.
+#[derive(Parser, Debug)]
+struct Args {
+    /// Seconds to wait between update cycles.
+    #[arg(long, default_value_t = 30)]
+    refresh: u64,
+
+    /// Directory used to cache rendered per-radar placefiles.
+    #[arg(long, default_value = "./cache/")]
+    cache_dir: PathBuf,
+
+    /// Path the combined placefile is written to.
+    #[arg(long, default_value = "vwp_hodographs")]
+    output: PathBuf,
+
+    /// Drop cached radars older than this many minutes.
+    #[arg(long, default_value_t = 20)]
+    max_age: i64,
+
+    /// Comma-separated radar site IDs to restrict updates to (default: all sites).
+    #[arg(long, value_delimiter = ',')]
+    radars: Option<Vec<String>>,
+
+    /// Run a single update cycle and exit instead of looping.
+    #[arg(long)]
+    once: bool,
+
+    /// Output format for the rendered hodographs.
+    #[arg(long, value_enum, default_value_t = Format::GrLevelX)]
+    format: Format,
+
+    /// How many radars to fetch/render at once.
+    #[arg(long, default_value_t = 8)]
+    concurrency: usize,
+}
+
 fn main() -> Result<()> {
-    if !Path::new("./cache/").exists() {
-        std::fs::create_dir("./cache/").expect("Could not create cache directory.");
+    let args = Args::parse();
+
+    if !args.cache_dir.exists() {
+        std::fs::create_dir(&args.cache_dir).expect("Could not create cache directory.");
+    }
+
+    let client = VadClient::new(Config {
+        cache_dir: args.cache_dir,
+        output: args.output,
+        max_age: args.max_age,
+        radars: args.radars,
+        format: args.format.into(),
+        concurrency: args.concurrency,
+    });
+
+    if args.once {
+        return client.update();
     }
 
     loop {
-        VadClient.update()?;
+        client.update()?;
 
-        std::thread::sleep(std::time::Duration::from_secs(30));
+        std::thread::sleep(Duration::from_secs(args.refresh));
     }
 }
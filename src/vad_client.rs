@@ -1,27 +1,157 @@
+use crate::hodograph_writer::{
+    GeoJsonWriter, GrLevelXWriter, HodographWriter, KmlWriter, SegmentMeta,
+};
+use crate::vad_params::Comp;
 use crate::{Result, VadFile};
 use chrono::{DateTime, NaiveDateTime, Utc};
 use lazy_static::lazy_static;
 use regex::Regex;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
 
 const BASE_URL: &str = "http://tgftp.nws.noaa.gov/SL.us008001/DF.of/DC.radar/DS.48vwp";
 
-fn get_color(altitude: f32) -> String {
+// Capped exponential backoff for a single radar's HTTP fetch.
+const MAX_FETCH_RETRIES: u32 = 5;
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+
+// Outcome of a `download_new` batch, so a single slow/broken radar doesn't
+// hide progress made on the rest of the batch.
+#[derive(Debug, Default)]
+pub struct UpdateSummary {
+    pub succeeded: Vec<String>,
+    pub failed: Vec<(String, String)>,
+}
+
+fn fetch_with_backoff(url: &str) -> Result<Box<dyn Read + Send + 'static>> {
+    let mut backoff = INITIAL_BACKOFF;
+    let mut last_err = None;
+
+    for attempt in 0..MAX_FETCH_RETRIES {
+        match ureq::get(url).call() {
+            Ok(resp) => {
+                let is_gzip = resp
+                    .header("Content-Encoding")
+                    .is_some_and(|v| v.eq_ignore_ascii_case("gzip"));
+                let reader = resp.into_reader();
+
+                return Ok(if is_gzip {
+                    Box::new(flate2::read::GzDecoder::new(reader))
+                } else {
+                    reader
+                });
+            }
+            Err(e) => {
+                last_err = Some(e);
+                if attempt + 1 == MAX_FETCH_RETRIES {
+                    break;
+                }
+                std::thread::sleep(backoff);
+                backoff *= 2;
+            }
+        }
+    }
+
+    Err(last_err.unwrap().into())
+}
+
+// Magic bytes identifying a compressed cache entry, so older uncompressed
+// cache files (written before compression was added) still load.
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+const GZIP_MAGIC: [u8; 2] = [0x1F, 0x8B];
+
+fn decompress_cache_entry(raw: Vec<u8>) -> Result<Vec<u8>> {
+    if raw.starts_with(&ZSTD_MAGIC) {
+        Ok(zstd::decode_all(raw.as_slice())?)
+    } else if raw.starts_with(&GZIP_MAGIC) {
+        let mut out = Vec::new();
+        flate2::read::GzDecoder::new(raw.as_slice()).read_to_end(&mut out)?;
+        Ok(out)
+    } else {
+        Ok(raw)
+    }
+}
+
+// Cache filenames look like `{radar}.{time}.{format_tag}.zst`, e.g.
+// `KTLX.1700000000.grlevelx.zst` — tagging entries with the format they were
+// rendered in means a cache left over from a run with a different
+// `--format` is never mistaken for (and concatenated with) the current run's.
+fn format_tag(format: OutputFormat) -> &'static str {
+    match format {
+        OutputFormat::GrLevelX => "grlevelx",
+        OutputFormat::GeoJson => "geojson",
+        OutputFormat::Kml => "kml",
+    }
+}
+
+fn cache_timestamp(filename: &str) -> Result<i64> {
+    filename
+        .strip_suffix(".zst")
+        .unwrap_or(filename)
+        .split('.')
+        .nth(1)
+        .unwrap()
+        .parse::<i64>()
+        .map_err(Into::into)
+}
+
+fn cache_format(filename: &str) -> Option<&str> {
+    filename
+        .strip_suffix(".zst")
+        .unwrap_or(filename)
+        .split('.')
+        .nth(2)
+}
+
+fn get_color(altitude: f32) -> (u8, u8, u8) {
     match altitude {
-        x if x < 1. => "220 0 220".to_string(),
-        x if x < 3. => "255 0 0".to_string(),
-        x if x < 6. => "0 255 0".to_string(),
-        x if x < 9. => "255 255 0".to_string(),
-        _ => "0 255 255".to_string(),
+        x if x < 1. => (220, 0, 220),
+        x if x < 3. => (255, 0, 0),
+        x if x < 6. => (0, 255, 0),
+        x if x < 9. => (255, 255, 0),
+        _ => (0, 255, 255),
     }
 }
 
-pub struct VadClient;
+// Output format for the rendered hodographs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    GrLevelX,
+    GeoJson,
+    Kml,
+}
+
+// Runtime settings for a VadClient, populated from the command line.
+pub struct Config {
+    pub cache_dir: PathBuf,
+    pub output: PathBuf,
+    pub max_age: i64,
+    pub radars: Option<Vec<String>>,
+    pub format: OutputFormat,
+    // How many radars to fetch/render at once.
+    pub concurrency: usize,
+}
+
+pub struct VadClient {
+    config: Config,
+}
 
 impl VadClient {
+    pub fn new(config: Config) -> Self {
+        Self { config }
+    }
+
     pub fn update(&self) -> Result<()> {
-        if self.download_new()? {
+        let summary = self.download_new()?;
+
+        for (radar, err) in &summary.failed {
+            eprintln!("Error updating {radar}: {err}");
+        }
+
+        if !summary.succeeded.is_empty() {
             self.create_placefile()?;
         }
 
@@ -29,10 +159,33 @@ impl VadClient {
     }
 
     fn write_radar(&self, placefile: &mut impl Write, radar: &str) -> Result<()> {
+        match self.config.format {
+            OutputFormat::GrLevelX => {
+                let mut writer = GrLevelXWriter::default();
+                self.render_radar(&mut writer, radar)?;
+                writer.finish(placefile)
+            }
+            OutputFormat::GeoJson => {
+                let mut writer = GeoJsonWriter::default();
+                self.render_radar(&mut writer, radar)?;
+                writer.finish(placefile)
+            }
+            OutputFormat::Kml => {
+                let mut writer = KmlWriter::default();
+                self.render_radar(&mut writer, radar)?;
+                writer.finish(placefile)
+            }
+        }
+    }
+
+    // Projects the profile into the geometry a `HodographWriter` renders.
+    // The lat/lon projection math (`location.0.to_radians().cos()` scaling)
+    // lives here once, shared by every output format.
+    fn render_radar(&self, writer: &mut impl HodographWriter, radar: &str) -> Result<()> {
         let is_tdwr = radar.starts_with('t');
 
         let url = format!("{BASE_URL}/SI.{radar}/sn.last");
-        let reader = ureq::get(&url).call()?.into_reader();
+        let reader = fetch_with_backoff(&url)?;
         let vad_file = VadFile::from_reader(reader)?;
 
         if vad_file.data.prof.len() < 2 {
@@ -58,68 +211,104 @@ impl VadClient {
             }
         };
 
-        // Draw cirlces
+        let lon_scale = vad_file.location.0.to_radians().cos();
+        let project = |u: f32, v: f32| -> (f32, f32) {
+            (
+                vad_file.location.0 + u * lon_scale * size,
+                vad_file.location.1 + v * size,
+            )
+        };
+
+        // Draw circles
         for i in (20..=max_size as u32).step_by(20) {
-            writeln!(placefile, "Color: 100 100 100")?;
-            writeln!(placefile, "Line: 1, 0")?;
+            let points: Vec<(f32, f32)> = (0..=60)
+                .map(|a| {
+                    let angle = 2.0 * std::f32::consts::PI * a as f32 / 60.;
+                    project(i as f32 * angle.cos(), i as f32 * angle.sin())
+                })
+                .collect();
+
+            writer.ring(&points)?;
+        }
 
-            for a in 0..=60 {
-                let angle = 2.0 * std::f32::consts::PI * a as f32 / 60.;
-                let x = vad_file.location.0
-                    + i as f32 * angle.cos() * vad_file.location.0.to_radians().cos() * size;
-                let y = vad_file.location.1 + i as f32 * angle.sin() * size;
+        let bunkers_motion = vad_file.data.bunkers();
 
-                writeln!(placefile, "{x}, {y}")?;
-            }
+        let bunkers = bunkers_motion.map_or(("NA".into(), "NA".into()), |b| {
+            (b.0.to_string(), b.1.to_string())
+        });
 
-            writeln!(placefile, "End:\n")?;
-        }
-
-        // let bunkers = vad_file
-        //     .data
-        //     .bunkers()
-        //     .map_or(("NA".into(), "NA".into()), |b| {
-        //         (b.0.to_string(), b.1.to_string())
-        //     });
+        let (srh_1km, srh_3km) = bunkers_motion
+            .map(|(rm, _)| Comp::from(rm))
+            .map_or((None, None), |motion| {
+                (
+                    vad_file.data.helicity(0., 1., motion),
+                    vad_file.data.helicity(0., 3., motion),
+                )
+            });
 
         let text = format!(
-            "VWP valid {} UTC",
+            "VWP valid {} UTC, mean wind {}, Bunkers RM {} / LM {}, SRH 0-1km/0-3km {}/{} m2/s2",
             vad_file.time.format("%m/%d/%Y %H%M"),
-            // vad_file
-            //     .data
-            //     .mean_wind(6.)
-            //     .map_or("NA".into(), |b| b.to_string()),
-            // bunkers.0,
-            // bunkers.1
+            vad_file
+                .data
+                .mean_wind(6.)
+                .map_or("NA".into(), |b| b.to_string()),
+            bunkers.0,
+            bunkers.1,
+            srh_1km.map_or("NA".into(), |v| format!("{v:.0}")),
+            srh_3km.map_or("NA".into(), |v| format!("{v:.0}")),
         );
 
-        let mut draw_color = String::new();
+        if let Some((rm, lm)) = bunkers_motion {
+            let rm_comp = Comp::from(rm).flip();
+            let lm_comp = Comp::from(lm).flip();
 
-        for (i, m) in vad_file.data.prof.iter().enumerate() {
-            let components = m.comp().flip();
+            writer.marker(project(rm_comp.u(), rm_comp.v()), "Bunkers RM")?;
+            writer.marker(project(lm_comp.u(), lm_comp.v()), "Bunkers LM")?;
+        }
 
-            let x = vad_file.location.0
-                + components.u() * vad_file.location.0.to_radians().cos() * size;
-            let y = vad_file.location.1 + components.v() * size;
+        let mut segment_points = Vec::new();
+        let mut segment_color = get_color(vad_file.data.prof[0].altitude);
+        let mut segment_start = &vad_file.data.prof[0];
 
+        for (i, m) in vad_file.data.prof.iter().enumerate() {
+            let components = m.comp().flip();
+            let point = project(components.u(), components.v());
             let color = get_color(m.altitude);
 
-            if i == 0 {
-                write!(placefile, "Color: {color}\nLine: 3, 0, \"{text}\"\n")?;
-                draw_color = color;
-            } else if color != draw_color && i != vad_file.data.prof.len() - 1 {
-                // Connect and finish last line
-                writeln!(placefile, "{x}, {y}\nEnd:\n")?;
-
-                // Start new line
-                write!(placefile, "Color: {color}\nLine: 3, 0, \"{text}\"\n")?;
-                draw_color = color;
+            if i != 0 && color != segment_color && i != vad_file.data.prof.len() - 1 {
+                // Connect and finish the segment that was in progress
+                segment_points.push(point);
+                writer.segment(
+                    &segment_points,
+                    segment_color,
+                    &text,
+                    SegmentMeta {
+                        altitude: segment_start.altitude,
+                        wind_dir: segment_start.wind_dir,
+                        wind_spd: segment_start.wind_spd,
+                    },
+                )?;
+
+                // Start a new segment
+                segment_points = Vec::new();
+                segment_color = color;
+                segment_start = m;
             }
 
-            writeln!(placefile, "{x}, {y}")?;
+            segment_points.push(point);
         }
 
-        writeln!(placefile, "End:\n")?;
+        writer.segment(
+            &segment_points,
+            segment_color,
+            &text,
+            SegmentMeta {
+                altitude: segment_start.altitude,
+                wind_dir: segment_start.wind_dir,
+                wind_spd: segment_start.wind_spd,
+            },
+        )?;
 
         Ok(())
     }
@@ -163,81 +352,153 @@ impl VadClient {
             "Length of radars does not match length of times."
         );
 
-        let map = HashMap::from_iter(radars.into_iter().zip(times.into_iter()));
+        let mut map: HashMap<String, i64> =
+            HashMap::from_iter(radars.into_iter().zip(times));
+
+        if let Some(wanted) = &self.config.radars {
+            map.retain(|radar, _| wanted.contains(radar));
+        }
 
         Ok(map)
     }
 
-    fn cache_radar(&self, radar: &str, time: i64) -> Result<()> {
-        let path = format!("./cache/{radar}.{time}");
+    // Renders and caches `radar`, removing its stale cache entry (if any)
+    // only once the new one has been written successfully — a failed fetch
+    // must never leave a radar's old data mislabeled as fresh.
+    fn cache_radar(&self, radar: &str, time: i64, stale: Option<&str>) -> Result<()> {
+        let path = self.config.cache_dir.join(format!(
+            "{radar}.{time}.{}.zst",
+            format_tag(self.config.format)
+        ));
         let mut file = Vec::new();
-        match self.write_radar(&mut file, radar) {
-            Ok(_) => std::fs::File::create(path)?.write_all(&file)?,
-            Err(_) => {
-                // eprintln!("Error: {e}");
-                return Ok(());
-            }
+        self.write_radar(&mut file, radar)?;
+
+        let compressed = zstd::encode_all(file.as_slice(), 0)?;
+        std::fs::File::create(path)?.write_all(&compressed)?;
+
+        if let Some(stale) = stale {
+            std::fs::remove_file(self.config.cache_dir.join(stale))?;
         }
 
         Ok(())
     }
 
-    fn download_new(&self) -> Result<bool> {
-        let files: Vec<String> = std::fs::read_dir("./cache/")?
+    fn download_new(&self) -> Result<UpdateSummary> {
+        let files: Vec<String> = std::fs::read_dir(&self.config.cache_dir)?
             .map(|d| d.unwrap().file_name().to_str().unwrap().to_string())
             .collect();
 
-        let mut new_files = false;
+        let mut jobs: Vec<(String, i64, Option<String>)> = Vec::new();
 
-        let times = self.fetch_times().unwrap();
+        let times = self.fetch_times()?;
         for (radar, time) in times {
             let old_file = files.iter().find(|f| f.starts_with(&radar));
 
-            if let Some(f) = old_file {
-                // If new file
-                if f.split('.').last().unwrap().parse::<i64>()? != time {
-                    let old = format!("./cache/{f}");
-                    let new = format!("./cache/{radar}.{time}");
-
-                    std::fs::rename(old, new)?;
-                    self.cache_radar(&radar, time).unwrap();
-                    new_files = true;
+            // A cache entry also needs refreshing (not just reusing) if it
+            // was rendered in a different `--format` than this run's.
+            match old_file {
+                Some(f)
+                    if cache_timestamp(f)? != time
+                        || cache_format(f) != Some(format_tag(self.config.format)) =>
+                {
+                    jobs.push((radar, time, Some(f.clone())))
                 }
-            } else {
-                // No file exists
-                self.cache_radar(&radar, time).unwrap();
+                Some(_) => {}
+                None => jobs.push((radar, time, None)),
             }
         }
 
-        Ok(new_files)
+        Ok(self.fetch_all(jobs))
+    }
+
+    // Fetch and render `jobs` across a bounded pool of worker threads so one
+    // slow or broken radar doesn't stall the rest of the batch.
+    fn fetch_all(&self, jobs: Vec<(String, i64, Option<String>)>) -> UpdateSummary {
+        let num_workers = self.config.concurrency.min(jobs.len().max(1));
+
+        let queue = Mutex::new(VecDeque::from(jobs));
+        let succeeded = Mutex::new(Vec::new());
+        let failed = Mutex::new(Vec::new());
+
+        std::thread::scope(|scope| {
+            for _ in 0..num_workers {
+                scope.spawn(|| loop {
+                    let Some((radar, time, stale)) = queue.lock().unwrap().pop_front() else {
+                        break;
+                    };
+
+                    match self.cache_radar(&radar, time, stale.as_deref()) {
+                        Ok(()) => succeeded.lock().unwrap().push(radar),
+                        Err(e) => failed.lock().unwrap().push((radar, e.to_string())),
+                    }
+                });
+            }
+        });
+
+        UpdateSummary {
+            succeeded: succeeded.into_inner().unwrap(),
+            failed: failed.into_inner().unwrap(),
+        }
     }
 
     fn create_placefile(&self) -> Result<()> {
-        let mut bytes = Vec::new();
-        for file_result in std::fs::read_dir("./cache/")? {
+        let mut fragments = Vec::new();
+        for file_result in std::fs::read_dir(&self.config.cache_dir)? {
             let file = file_result?;
+            let filename = file.file_name().into_string().unwrap();
+
+            // Cache entries rendered in another `--format` must never be
+            // concatenated into this run's output. Untagged `{radar}.{time}`
+            // entries predate the format tag and were always GRLevelX.
+            let entry_format =
+                cache_format(&filename).unwrap_or(format_tag(OutputFormat::GrLevelX));
+            if entry_format != format_tag(self.config.format) {
+                continue;
+            }
 
             // Timestamp
-            let time = file
-                .file_name()
-                .into_string()
-                .unwrap()
-                .split('.')
-                .last()
-                .unwrap()
-                .parse::<i64>()?;
-
-            // If find is from last 20 minuties
+            let time = cache_timestamp(&filename)?;
+
+            // If fresh enough to include
             let datetime = DateTime::<Utc>::from_utc(NaiveDateTime::from_timestamp(time, 0), Utc);
-            if (datetime - Utc::now()).num_minutes() < 20 {
-                std::fs::File::open(file.path())?.read_to_end(&mut bytes)?;
+            if (Utc::now() - datetime).num_minutes() < self.config.max_age {
+                let mut raw = Vec::new();
+                std::fs::File::open(file.path())?.read_to_end(&mut raw)?;
+                fragments.push(decompress_cache_entry(raw)?);
             }
         }
 
-        let mut placefile = std::fs::File::create("vwp_hodographs")?;
-        writeln!(&mut placefile, "Title: VWP Hodographs")?;
-        writeln!(&mut placefile, "Refresh: 1\n")?;
-        placefile.write_all(&bytes)?;
+        let mut placefile = std::fs::File::create(&self.config.output)?;
+
+        match self.config.format {
+            OutputFormat::GrLevelX => {
+                writeln!(&mut placefile, "Title: VWP Hodographs")?;
+                writeln!(&mut placefile, "Refresh: 1\n")?;
+                for bytes in fragments {
+                    placefile.write_all(&bytes)?;
+                }
+            }
+            OutputFormat::GeoJson => {
+                write!(&mut placefile, r#"{{"type":"FeatureCollection","features":["#)?;
+                for (i, bytes) in fragments.iter().filter(|b| !b.is_empty()).enumerate() {
+                    if i > 0 {
+                        write!(&mut placefile, ",")?;
+                    }
+                    placefile.write_all(bytes)?;
+                }
+                write!(&mut placefile, "]}}")?;
+            }
+            OutputFormat::Kml => {
+                write!(
+                    &mut placefile,
+                    r#"<?xml version="1.0" encoding="UTF-8"?><kml xmlns="http://www.opengis.net/kml/2.2"><Document>"#
+                )?;
+                for bytes in fragments {
+                    placefile.write_all(&bytes)?;
+                }
+                write!(&mut placefile, "</Document></kml>")?;
+            }
+        }
 
         Ok(())
     }
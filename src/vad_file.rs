@@ -1,51 +1,57 @@
 use crate::Result;
 use crate::{VadError, VadMessage, VadProfile};
-use std::io::Read;
+use std::io::{self, Read};
 
 use chrono::{DateTime, Duration, TimeZone, Utc};
 
-// Possible use a read trait instead of a macro in the future?
-macro_rules! read {
-    ($reader:expr, $ty:ty, $len:expr) => {{
-        let mut buf = [0u8; $len * std::mem::size_of::<$ty>()];
-        let res: [$ty; $len] = $reader.read_exact(&mut buf).map(|_| {
-            buf.chunks_exact(std::mem::size_of::<$ty>())
-                .map(|v| <$ty>::from_be_bytes(v.try_into().unwrap()))
-                .collect::<Vec<$ty>>()
-                .try_into()
-                .unwrap()
-        })?;
-
-        res
-    }};
-
-    ($reader:expr, $ty:ty) => {{
-        let mut buf = [0u8; std::mem::size_of::<$ty>()];
-        $reader
-            .read_exact(&mut buf)
-            .map(|_| <$ty>::from_be_bytes(buf))?
-    }};
+pub trait FromReader: Sized {
+    fn from_reader<R: Read>(r: &mut R) -> io::Result<Self>;
 }
 
-macro_rules! read_vec {
-    ($reader:expr, $ty:ty, $len:expr) => {{
-        let mut buf = vec![0u8; $len * std::mem::size_of::<$ty>()];
-        $reader.read(buf.as_mut_slice()).map(|_| {
-            buf.chunks_exact(std::mem::size_of::<$ty>())
-                .map(|v| <$ty>::from_be_bytes(v.try_into().unwrap()))
-                .collect::<Vec<_>>()
-        })?
-    }};
+macro_rules! impl_from_reader {
+    ($ty:ty) => {
+        impl FromReader for $ty {
+            fn from_reader<R: Read>(r: &mut R) -> io::Result<Self> {
+                let mut buf = [0u8; std::mem::size_of::<$ty>()];
+                r.read_exact(&mut buf)?;
+                Ok(<$ty>::from_be_bytes(buf))
+            }
+        }
+    };
+}
+
+impl_from_reader!(i8);
+impl_from_reader!(i16);
+impl_from_reader!(i32);
+impl_from_reader!(u8);
+impl_from_reader!(f32);
+
+fn read_array<T: FromReader, R: Read, const N: usize>(r: &mut R) -> io::Result<[T; N]> {
+    let mut out: Vec<T> = Vec::with_capacity(N);
+    for _ in 0..N {
+        out.push(T::from_reader(r)?);
+    }
+
+    match out.try_into() {
+        Ok(arr) => Ok(arr),
+        Err(_) => unreachable!("array was built with exactly N elements"),
+    }
+}
+
+fn read_vec<T: FromReader, R: Read>(r: &mut R, n: usize) -> io::Result<Vec<T>> {
+    let mut out = Vec::with_capacity(n);
+    for _ in 0..n {
+        out.push(T::from_reader(r)?);
+    }
+
+    Ok(out)
 }
 
-macro_rules! read_string {
-    ($reader:expr, $len:expr) => {{
-        let mut buf = vec![0u8; $len];
+fn read_string(r: &mut impl Read, len: usize) -> io::Result<String> {
+    let mut buf = vec![0u8; len];
+    r.read_exact(&mut buf)?;
 
-        $reader
-            .read_exact(buf.as_mut_slice())
-            .map(|_| buf.into_iter().map(|v| v as char).collect::<String>())?
-    }};
+    Ok(buf.into_iter().map(|v| v as char).collect())
 }
 
 pub struct VadFile {
@@ -113,53 +119,53 @@ fn get_data(messages: Vec<Vec<String>>) -> Result<VadProfile> {
 }
 
 fn read_headers(reader: &mut impl Read) -> Result<()> {
-    let _wmo_header = read!(reader, u8, 30);
-    let _message_date = read!(reader, i16);
-    let _message_code = read!(reader, i16);
-    let _message_time = read!(reader, i32);
-    let _message_length = read!(reader, i32);
-    let _source_id = read!(reader, i16);
-    let _dest_id = read!(reader, i16);
-    let _num_blocks = read!(reader, i16);
+    let _wmo_header: [u8; 30] = read_array(reader)?;
+    let _message_date = i16::from_reader(reader)?;
+    let _message_code = i16::from_reader(reader)?;
+    let _message_time = i32::from_reader(reader)?;
+    let _message_length = i32::from_reader(reader)?;
+    let _source_id = i16::from_reader(reader)?;
+    let _dest_id = i16::from_reader(reader)?;
+    let _num_blocks = i16::from_reader(reader)?;
 
     Ok(())
 }
 
 fn read_desc_block(reader: &mut impl Read) -> Result<(DateTime<Utc>, (f32, f32), bool)> {
     // Block separator
-    read!(reader, i16);
+    i16::from_reader(reader)?;
 
-    let lat = read!(reader, i32) as f32 / 1000.0;
-    let lon = read!(reader, i32) as f32 / 1000.0;
+    let lat = i32::from_reader(reader)? as f32 / 1000.0;
+    let lon = i32::from_reader(reader)? as f32 / 1000.0;
 
-    let _radar_elev = read!(reader, i16);
+    let _radar_elev = i16::from_reader(reader)?;
 
-    let product_code = read!(reader, i16);
+    let product_code = i16::from_reader(reader)?;
     assert!(
         product_code == 48,
         "This is not a VWP file, found code {product_code} instead."
     );
 
-    let _operation_mode = read!(reader, i16);
-    let _vcp = read!(reader, i16);
-    let _req_sequence_number = read!(reader, i16);
-    let _vol_sequence_number = read!(reader, i16);
+    let _operation_mode = i16::from_reader(reader)?;
+    let _vcp = i16::from_reader(reader)?;
+    let _req_sequence_number = i16::from_reader(reader)?;
+    let _vol_sequence_number = i16::from_reader(reader)?;
 
-    let scan_date = read!(reader, i16);
-    let scan_time = read!(reader, i32);
+    let scan_date = i16::from_reader(reader)?;
+    let scan_time = i32::from_reader(reader)?;
 
-    let _product_date = read!(reader, i16);
-    let _product_time = read!(reader, i32);
+    let _product_date = i16::from_reader(reader)?;
+    let _product_time = i32::from_reader(reader)?;
 
     // Unused variables
-    read!(reader, i16, 27);
+    let _unused: [i16; 27] = read_array(reader)?;
 
-    let _version = read!(reader, i8);
-    let _spot_blank = read!(reader, i8);
+    let _version = i8::from_reader(reader)?;
+    let _spot_blank = i8::from_reader(reader)?;
 
-    let offset_symbology = read!(reader, i32);
-    let _offset_graphic = read!(reader, i32);
-    let offset_tabular = read!(reader, i32);
+    let offset_symbology = i32::from_reader(reader)?;
+    let _offset_graphic = i32::from_reader(reader)?;
+    let offset_tabular = i32::from_reader(reader)?;
 
     let time = Utc.ymd(1969, 12, 31).and_hms(0, 0, 0)
         + Duration::days(scan_date as i64)
@@ -174,69 +180,69 @@ fn read_desc_block(reader: &mut impl Read) -> Result<(DateTime<Utc>, (f32, f32),
 
 fn read_symbology(reader: &mut impl Read) -> Result<()> {
     // Block separator
-    read!(reader, i16);
+    i16::from_reader(reader)?;
 
-    let block_id = read!(reader, i16);
+    let block_id = i16::from_reader(reader)?;
     if block_id != 1 {
         return Err(VadError::SymbologyBlockError.into());
     }
 
-    let _block_length = read!(reader, i32);
-    let _num_layers = read!(reader, i16);
-    let _layer_sep = read!(reader, i16);
-    let layer_num_bytes = read!(reader, i32);
-    let _block_data = read_vec!(reader, i16, layer_num_bytes as usize / 2);
+    let _block_length = i32::from_reader(reader)?;
+    let _num_layers = i16::from_reader(reader)?;
+    let _layer_sep = i16::from_reader(reader)?;
+    let layer_num_bytes = i32::from_reader(reader)?;
+    let _block_data: Vec<i16> = read_vec(reader, layer_num_bytes as usize / 2)?;
 
     Ok(())
 }
 
 fn read_tabular(reader: &mut impl Read) -> Result<Vec<Vec<String>>> {
     // Block separator
-    read!(reader, i16);
+    i16::from_reader(reader)?;
 
-    let block_id = read!(reader, i16);
+    let block_id = i16::from_reader(reader)?;
     if block_id != 3 {
         return Err(VadError::TabularBlockError.into());
     }
 
-    let _block_size = read!(reader, i32);
+    let _block_size = i32::from_reader(reader)?;
 
     // Unknown bytes
-    read!(reader, u8, 30);
+    let _unknown: [u8; 30] = read_array(reader)?;
 
-    let _product_code = read!(reader, i16);
-    let _operation_mode = read!(reader, i16);
-    let _vcp = read!(reader, i16);
-    let _req_seq_number = read!(reader, i16);
-    let _vol_seq_numbe = read!(reader, i16);
+    let _product_code = i16::from_reader(reader)?;
+    let _operation_mode = i16::from_reader(reader)?;
+    let _vcp = i16::from_reader(reader)?;
+    let _req_seq_number = i16::from_reader(reader)?;
+    let _vol_seq_numbe = i16::from_reader(reader)?;
 
-    let _scan_date = read!(reader, i16);
-    let _scan_time = read!(reader, i32);
-    let _product_date = read!(reader, i16);
-    let _product_time = read!(reader, i32);
+    let _scan_date = i16::from_reader(reader)?;
+    let _scan_time = i32::from_reader(reader)?;
+    let _product_date = i16::from_reader(reader)?;
+    let _product_time = i32::from_reader(reader)?;
 
     // Unused variables
-    read!(reader, i16, 27);
+    let _unused: [i16; 27] = read_array(reader)?;
 
-    let _version = read!(reader, i8);
-    let _spot_blank = read!(reader, i8);
+    let _version = i8::from_reader(reader)?;
+    let _spot_blank = i8::from_reader(reader)?;
 
-    let _offset_symbology = read!(reader, i32);
-    let _offset_graphic = read!(reader, i32);
-    let _offset_tabular = read!(reader, i32);
+    let _offset_symbology = i32::from_reader(reader)?;
+    let _offset_graphic = i32::from_reader(reader)?;
+    let _offset_tabular = i32::from_reader(reader)?;
     // Block separator
-    read!(reader, i16);
-    let num_pages = read!(reader, i16);
+    i16::from_reader(reader)?;
+    let num_pages = i16::from_reader(reader)?;
 
     let mut messages = Vec::new();
 
     for _ in 0..num_pages {
         let mut message = Vec::new();
-        let mut num_chars = read!(reader, i16);
+        let mut num_chars = i16::from_reader(reader)?;
 
         while num_chars != -1 {
-            message.push(read_string!(reader, num_chars as usize));
-            num_chars = read!(reader, i16);
+            message.push(read_string(reader, num_chars as usize)?);
+            num_chars = i16::from_reader(reader)?;
         }
 
         messages.push(message);
@@ -1,6 +1,8 @@
 use std::ops::{Add, Sub};
 
+#[derive(Clone, Copy)]
 pub struct Vector(f32, f32);
+#[derive(Clone, Copy)]
 pub struct Comp(f32, f32);
 
 impl Comp {
@@ -133,9 +135,11 @@ impl VadProfile {
             return None;
         }
 
-        let xs: Vec<f32> = (self.prof[0].altitude.ceil() as u32..top as u32)
-            .map(|v| v as f32 / 1000.)
-            .collect();
+        // Altitudes are tracked in km, but we sample every meter within the
+        // layer for the average, so the range itself has to run in meters.
+        let bot_m = (self.prof[0].altitude * 1000.).ceil() as u32;
+        let top_m = (top * 1000.) as u32;
+        let xs: Vec<f32> = (bot_m..top_m).map(|v| v as f32 / 1000.).collect();
 
         let (u, v): (Vec<f32>, Vec<f32>) = xs
             .iter()
@@ -144,8 +148,8 @@ impl VadProfile {
 
         Some(
             Comp(
-                v.into_iter().sum::<f32>() / xs.len() as f32,
                 u.into_iter().sum::<f32>() / xs.len() as f32,
+                v.into_iter().sum::<f32>() / xs.len() as f32,
             )
             .into(),
         )
@@ -176,17 +180,51 @@ impl VadProfile {
         Some((rs.into(), ls.into()))
     }
 
-    // fn helicity(&self, profile: Vec<VadMessage>, bottom: f32, top: f32) -> f32 {}
+    pub fn helicity(&self, bot: f32, top: f32, storm_motion: Comp) -> Option<f32> {
+        if self.prof.is_empty() || top >= *self.altitude().last()? {
+            return None;
+        }
+
+        // `bot`/`top` are km, but we sample every meter within the layer, so
+        // the range itself has to run in meters.
+        let bot_m = (bot * 1000.).ceil() as u32;
+        let top_m = (top * 1000.) as u32;
+        let xs: Vec<f32> = (bot_m..top_m).map(|v| v as f32 / 1000.).collect();
+
+        let comps: Vec<Comp> = xs
+            .iter()
+            .map(|&v| self.interp_height(v))
+            .collect::<Option<_>>()?;
+
+        let c_u = storm_motion.u().kts_to_ms();
+        let c_v = storm_motion.v().kts_to_ms();
+
+        let mut srh = 0.;
+
+        for pair in comps.windows(2) {
+            let (u0, v0) = (pair[0].u().kts_to_ms(), pair[0].v().kts_to_ms());
+            let (u1, v1) = (pair[1].u().kts_to_ms(), pair[1].v().kts_to_ms());
+
+            srh += (u1 - c_u) * (v0 - c_v) - (u0 - c_u) * (v1 - c_v);
+        }
+
+        Some(srh)
+    }
 }
 
 trait ConvertUnits {
     fn ms_to_kts(self) -> Self;
+    fn kts_to_ms(self) -> Self;
 }
 
 impl ConvertUnits for f32 {
     fn ms_to_kts(self) -> f32 {
         self * 1.94384
     }
+
+    fn kts_to_ms(self) -> f32 {
+        self / 1.94384
+    }
 }
 
 fn interp(mut x: f32, xp: &[f32], yp: &[f32]) -> Option<f32> {
@@ -203,3 +241,48 @@ fn interp(mut x: f32, xp: &[f32], yp: &[f32]) -> Option<f32> {
 
     Some(yp[i] + (x - xp[i]) * (yp[i + 1] - yp[i]) / (xp[i + 1] - xp[i]))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Linear shear from (180/20kt) at the surface to (270/40kt) at 4 km.
+    fn sheared_profile() -> VadProfile {
+        VadProfile {
+            prof: vec![
+                VadMessage {
+                    wind_dir: 180.,
+                    wind_spd: 20.,
+                    altitude: 0.,
+                },
+                VadMessage {
+                    wind_dir: 270.,
+                    wind_spd: 40.,
+                    altitude: 4.,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn mean_wind_averages_the_layer_not_just_the_surface() {
+        let profile = sheared_profile();
+        let Comp(u, v) = profile.mean_wind(3.).unwrap().into();
+
+        // The mean over the bottom 3 km of this linear profile works out to
+        // (15, 12.5) kt; a range bug that samples only the surface would
+        // instead return the surface wind's (0, 20).
+        assert!((u - 15.).abs() < 0.5, "u = {u}");
+        assert!((v - 12.5).abs() < 0.5, "v = {v}");
+    }
+
+    #[test]
+    fn helicity_is_non_trivial_for_a_sheared_profile() {
+        let profile = sheared_profile();
+        let srh = profile.helicity(0., 3., Comp(0., 0.)).unwrap();
+
+        // A range bug that collapses the sampled layer to nothing makes this
+        // always 0.0 regardless of the shear.
+        assert!(srh.abs() > 1.0, "srh = {srh}");
+    }
+}